@@ -1,17 +1,24 @@
 use std::io;
+use std::marker::PhantomData;
 use hsl::HSL;
 use pixelate::{Color, Image, Error};
 
+use crate::rng::{BlockiesRng, LegacySinRng};
 use crate::util::{create_image_data, hsl_to_rgb};
 
-/// Context struct for creating an classic 2-color Blockies.
+/// Context struct for creating an classic Blockies, with a background, a
+/// main color and a spot color.
+///
+/// Generic over the pseudo-random generator used to expand the seed, see
+/// [`BlockiesRng`]. Defaults to [`LegacySinRng`], the `sin`-based generator
+/// used by the reference JavaScript implementation.
 ///
 /// The best way to create it is by using the default trait:
 ///
 /// ```rust
 /// use blockies::Classic;
 ///
-/// let mut gen = Classic::default();
+/// let mut gen: Classic = Classic::default();
 /// let mut png = Vec::new();
 ///
 /// gen.scale = 8;
@@ -20,83 +27,354 @@ use crate::util::{create_image_data, hsl_to_rgb};
 /// // `png` contains the PNG image of the blockies.
 /// assert!(png.len() > 0);
 /// ```
-pub struct Classic {
+pub struct Classic<R: BlockiesRng = LegacySinRng> {
 	/// Size of blockies (number of blocks per row in the image), default: 8
 	pub size: usize,
 	/// Pixel size (width and height) of a single block in the image, default: 16
 	pub scale: usize,
-	/// Foreground color of the image, default: None (derived from seed)
+	/// Main color of the image, default: None (derived from seed)
 	pub color: Option<Color>,
-	/// Background color of the image, default: None (white)
+	/// Background color of the image, default: None (derived from seed)
 	pub background_color: Option<Color>,
+	/// Spot color of the image, default: None (derived from seed)
+	pub spot_color: Option<Color>,
+	/// Bernoulli probability that a cell is filled (color or spot) rather
+	/// than background, default: `1.0 - 1.0 / 2.3` (~0.5652). Clamped into
+	/// `[0.0, 1.0]`. The default is not `0.5` because it has to reproduce
+	/// the background/color/spot thresholds of the canonical
+	/// `floor(rand() * 2.3)` formula exactly, not merely approximate them.
+	pub density: f64,
+	_rng: PhantomData<R>,
 }
 
-impl Classic {
+impl<R: BlockiesRng> Classic<R> {
 	/// Write the PNG image of the blockies for a given `seed` into a writer.
 	pub fn create_icon<W: io::Write>(&self, writer: W, seed: &[u8]) -> Result<(), Error> {
-		let mut seed = Seed::new(seed);
+		let (palette, pixels, grid_width) = self.render_data(seed);
+
+		Image {
+			palette: &palette,
+			pixels: &pixels,
+			width: grid_width,
+			scale: self.scale,
+		}.render(writer)
+	}
+
+	/// Compute the scaled RGBA pixel buffer for the blockies for a given
+	/// `seed`, without encoding it to PNG.
+	///
+	/// Returns `(width, height, pixels)`, where `pixels` holds `width * height`
+	/// RGBA samples in row-major order, suitable for uploading directly to a
+	/// GPU texture or GUI framework.
+	pub fn create_image_buffer(&self, seed: &[u8]) -> (usize, usize, Vec<[u8; 4]>) {
+		let (palette, pixels, grid_width) = self.render_data(seed);
+		let palette = [to_rgba(palette[0]), to_rgba(palette[1]), to_rgba(palette[2])];
+
+		let width = grid_width * self.scale;
+		let height = self.size * self.scale;
+
+		let mut buffer = Vec::with_capacity(width * height);
+
+		for row in pixels.chunks(grid_width) {
+			for _ in 0..self.scale {
+				for &index in row {
+					buffer.extend(std::iter::repeat_n(palette[index as usize], self.scale));
+				}
+			}
+		}
+
+		(width, height, buffer)
+	}
+
+	/// Write an ASCII/Unicode rendering of the blockies grid for a given
+	/// `seed` into a writer, one row of blocks per line, with no PNG
+	/// encoding involved.
+	///
+	/// `glyphs` maps a palette index (background, color, spot, in that
+	/// order) to a character; each block is drawn as two copies of its
+	/// glyph side by side to offset the narrowness of terminal cells. If
+	/// `glyphs` is empty, [`DEFAULT_GLYPHS`] is used instead.
+	pub fn create_ascii<W: io::Write>(&self, mut writer: W, seed: &[u8], glyphs: &[char]) -> io::Result<()> {
+		let (_, pixels, grid_width) = self.render_data(seed);
+		let glyphs = if glyphs.is_empty() { &DEFAULT_GLYPHS[..] } else { glyphs };
+
+		for row in pixels.chunks(grid_width) {
+			let mut line = String::with_capacity(grid_width * 2 + 1);
+
+			for &index in row {
+				let glyph = glyphs.get(index as usize).copied().unwrap_or(' ');
+				line.push(glyph);
+				line.push(glyph);
+			}
+
+			line.push('\n');
+			writer.write_all(line.as_bytes())?;
+		}
+
+		Ok(())
+	}
+
+	/// Write a single tiled PNG montage containing one blockies tile per
+	/// entry in `seeds` into a writer.
+	///
+	/// Tiles are laid out left to right in a grid of `columns` columns,
+	/// wrapping to a new row as needed, each rendered at the configured
+	/// `size`/`scale`. Colors are deduplicated into a single shared palette
+	/// of at most 256 entries, since pixel indices are `u8`; if `seeds`
+	/// contains enough distinct colors to exceed that, this returns
+	/// [`Error::PaletteTooBig`] instead of overflowing the index.
+	pub fn create_sheet<W: io::Write>(&self, writer: W, seeds: &[&[u8]], columns: usize) -> Result<(), Error> {
+		let columns = columns.max(1);
+		let rows = seeds.len().div_ceil(columns);
+
+		let tile_grid_width = self.size + self.size % 2;
+		let sheet_grid_width = tile_grid_width * columns;
 
-		let color = self.color.unwrap_or_else(|| seed.create_color());
-		let background_color = self.background_color.unwrap_or_else(|| pixelate::WHITE);
+		let mut palette: Vec<Color> = Vec::new();
+		let mut pixels = vec![0u8; sheet_grid_width * self.size * rows];
 
-		let palette = vec![background_color, color];
-		let pixels = create_image_data(self.size as usize, || seed.rand() >= 0.5);
+		for (i, seed) in seeds.iter().enumerate() {
+			let (tile_palette, tile_pixels, grid_width) = self.render_data(seed);
+
+			let mut indices = [0u8; 3];
+			for (slot, &color) in indices.iter_mut().zip(tile_palette.iter()) {
+				*slot = match palette.iter().position(|&existing| existing == color) {
+					Some(pos) => pos as u8,
+					None if palette.len() < 256 => {
+						palette.push(color);
+						(palette.len() - 1) as u8
+					}
+					None => return Err(Error::PaletteTooBig),
+				};
+			}
+
+			let x_offset = (i % columns) * tile_grid_width;
+			let y_offset = (i / columns) * self.size;
+
+			for (row_idx, row) in tile_pixels.chunks(grid_width).enumerate() {
+				let dest_start = (y_offset + row_idx) * sheet_grid_width + x_offset;
+
+				for (col_idx, &index) in row.iter().enumerate() {
+					pixels[dest_start + col_idx] = indices[index as usize];
+				}
+			}
+		}
 
 		Image {
 			palette: &palette,
 			pixels: &pixels,
-			width: self.size + self.size % 2,
+			width: sheet_grid_width,
 			scale: self.scale,
 		}.render(writer)
 	}
+
+	/// Compute the unscaled palette and pixel grid shared by all of this
+	/// generator's output methods.
+	fn render_data(&self, seed: &[u8]) -> ([Color; 3], Vec<u8>, usize) {
+		let mut rng = R::from_seed(seed);
+
+		// Draw order matches the reference implementation: main color first,
+		// then background, then spot, so seeded output lines up byte-for-byte.
+		let color = self.color.unwrap_or_else(|| create_color(&mut rng));
+		let background_color = self.background_color.unwrap_or_else(|| create_color(&mut rng));
+		let spot_color = self.spot_color.unwrap_or_else(|| create_color(&mut rng));
+
+		let palette = [background_color, color, spot_color];
+		let grid_width = self.size + self.size % 2;
+		let density = self.density.clamp(0.0, 1.0);
+		let pixels = create_image_data(self.size, || create_fill(&mut rng, density));
+
+		(palette, pixels, grid_width)
+	}
 }
 
-impl Default for Classic {
+impl<R: BlockiesRng> Default for Classic<R> {
 	fn default() -> Self {
 		Classic {
 			size: 8,
 			scale: 16,
 			color: None,
 			background_color: None,
+			spot_color: None,
+			density: 1.0 - 1.0 / 2.3,
+			_rng: PhantomData,
 		}
 	}
 }
 
-pub struct Seed {
-	randseed: f64,
+fn create_color<R: BlockiesRng>(rng: &mut R) -> Color {
+	let hsl = HSL {
+		h: (rng.next_f64() * 360.0).floor(),
+		s: (rng.next_f64() * 50.0 + 50.0) / 100.0,
+		l: (rng.next_f64() * 60.0 + 20.0) / 100.0,
+	};
+	hsl_to_rgb(hsl)
+}
+
+/// Default glyph palette used by [`Classic::create_ascii`] when `glyphs` is
+/// empty: background, main color, spot color.
+pub const DEFAULT_GLYPHS: [char; 3] = [' ', '#', '%'];
+
+/// Share of filled cells that land on the spot color rather than the main
+/// color, preserving the 10:3 color-to-spot ratio of the original fixed
+/// `floor(rand() * 2.3)` formula.
+const SPOT_SHARE: f64 = 3.0 / 13.0;
+
+fn create_fill<R: BlockiesRng>(rng: &mut R, density: f64) -> u8 {
+	let r = rng.next_f64();
+	let background_cutoff = 1.0 - density;
+
+	if r < background_cutoff {
+		0
+	} else if r < 1.0 - density * SPOT_SHARE {
+		1
+	} else {
+		2
+	}
+}
+
+fn to_rgba(color: Color) -> [u8; 4] {
+	match color {
+		Color::Rgb(r, g, b) => [r, g, b, 255],
+		Color::Rgba(r, g, b, a) => [r, g, b, a],
+	}
 }
 
-impl Seed {
-	fn new(seed: &[u8]) -> Self {
-		let mut randseed = 0u64;
+#[cfg(test)]
+mod tests {
+	use super::*;
 
-		for i in 0..seed.len() / 2 {
-			let h = ((seed[i * 2] as u64) << 8) | seed[i * 2 + 1] as u64;
-			randseed = randseed ^ h;
+	/// A test-only [`BlockiesRng`] that replays a fixed sequence of values,
+	/// so the fill/color thresholds can be probed precisely.
+	struct StubRng<'a>(std::iter::Copied<std::slice::Iter<'a, f64>>);
+
+	impl<'a> StubRng<'a> {
+		fn new(values: &'a [f64]) -> Self {
+			StubRng(values.iter().copied())
 		}
+	}
 
-		if seed.len() % 2 == 1 {
-			randseed = randseed ^ ((seed[seed.len() - 1] as u64) << 8);
+	impl<'a> BlockiesRng for StubRng<'a> {
+		fn from_seed(_seed: &[u8]) -> Self {
+			StubRng::new(&[])
 		}
 
-		Seed {
-			randseed: randseed as f64,
+		fn next_f64(&mut self) -> f64 {
+			self.0.next().expect("StubRng ran out of values")
 		}
 	}
 
-	fn rand(&mut self) -> f64 {
-		let n = (self.randseed.sin() + 1.0) / 2.0;
-		self.randseed += 1.0;
-		let r = n * 10000.0;
-		r - r.floor()
+	/// A test-only [`BlockiesRng`] that walks a low-discrepancy sequence
+	/// seeded from the input bytes, producing many distinct draws so the
+	/// `create_sheet` palette can be pushed past its 256-entry ceiling
+	/// deterministically.
+	struct SequentialRng(f64);
+
+	const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_894_9;
+
+	impl BlockiesRng for SequentialRng {
+		fn from_seed(seed: &[u8]) -> Self {
+			let sum: u64 = seed.iter().map(|&b| b as u64).sum();
+			SequentialRng((sum as f64 * GOLDEN_RATIO_CONJUGATE) % 1.0)
+		}
+
+		fn next_f64(&mut self) -> f64 {
+			self.0 = (self.0 + GOLDEN_RATIO_CONJUGATE) % 1.0;
+			self.0
+		}
+	}
+
+	#[test]
+	fn default_density_matches_canonical_thresholds() {
+		// At the default density, `create_fill` must reproduce the
+		// canonical `floor(rand() * 2.3)` thresholds exactly.
+		let density = Classic::<LegacySinRng>::default().density;
+		let epsilon = 1e-9;
+
+		let background = 1.0 / 2.3;
+		let color = 2.0 / 2.3;
+
+		assert_eq!(create_fill(&mut StubRng::new(&[background - epsilon]), density), 0);
+		assert_eq!(create_fill(&mut StubRng::new(&[background + epsilon]), density), 1);
+		assert_eq!(create_fill(&mut StubRng::new(&[color - epsilon]), density), 1);
+		assert_eq!(create_fill(&mut StubRng::new(&[color + epsilon]), density), 2);
+	}
+
+	#[test]
+	fn create_image_buffer_dimensions_match_scaled_grid() {
+		let gen: Classic = Classic::default();
+		let (width, height, buffer) = gen.create_image_buffer(b"hello world");
+
+		assert_eq!(width, (gen.size + gen.size % 2) * gen.scale);
+		assert_eq!(height, gen.size * gen.scale);
+		assert_eq!(buffer.len(), width * height);
+	}
+
+	#[test]
+	fn create_ascii_emits_one_line_per_row() {
+		let gen: Classic = Classic::default();
+		let mut out = Vec::new();
+		gen.create_ascii(&mut out, b"hello world", &[]).unwrap();
+
+		let text = String::from_utf8(out).unwrap();
+		let expected_width = (gen.size + gen.size % 2) * 2;
+
+		assert_eq!(text.lines().count(), gen.size);
+		assert!(text.lines().all(|line| line.chars().count() == expected_width));
 	}
 
-	fn create_color(&mut self) -> Color {
-		let hsl = HSL {
-			h: (self.rand() * 360.0).floor(),
-			s: (self.rand() * 50.0 + 50.0) / 100.0,
-			l: (self.rand() * 60.0 + 20.0) / 100.0,
+	#[test]
+	fn create_sheet_lays_tiles_out_in_the_requested_columns() {
+		let gen: Classic = Classic::default();
+		let seeds: &[&[u8]] = &[b"a", b"b", b"c"];
+
+		let mut sheet = Vec::new();
+		gen.create_sheet(&mut sheet, seeds, 2).unwrap();
+
+		let mut single = Vec::new();
+		gen.create_icon(&mut single, b"a").unwrap();
+
+		// 3 seeds over 2 columns is a 2x2 grid of tiles, strictly bigger
+		// than a single tile's icon.
+		assert!(sheet.len() > single.len());
+
+		// The PNG IHDR chunk holds the image width and height at bytes 16..24.
+		let dimensions = |png: &[u8]| {
+			let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+			let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+			(width, height)
 		};
-		hsl_to_rgb(hsl)
+		let (sheet_width, sheet_height) = dimensions(&sheet);
+		let (tile_width, tile_height) = dimensions(&single);
+
+		assert_eq!(sheet_width, tile_width * 2);
+		assert_eq!(sheet_height, tile_height * 2);
+	}
+
+	#[test]
+	fn create_sheet_reports_palette_too_big_instead_of_overflowing() {
+		let gen: Classic<SequentialRng> = Classic::default();
+
+		let seed_bytes: Vec<[u8; 4]> = (0u32..300).map(|i| i.to_be_bytes()).collect();
+		let seeds: Vec<&[u8]> = seed_bytes.iter().map(|s| s.as_slice()).collect();
+
+		let mut out = Vec::new();
+		let result = gen.create_sheet(&mut out, &seeds, 20);
+
+		assert_eq!(result, Err(Error::PaletteTooBig));
+	}
+
+	#[test]
+	fn custom_rng_can_be_plugged_in_via_the_trait() {
+		let legacy: Classic<LegacySinRng> = Classic::default();
+		let sequential: Classic<SequentialRng> = Classic::default();
+
+		let mut legacy_png = Vec::new();
+		let mut sequential_png = Vec::new();
+
+		legacy.create_icon(&mut legacy_png, b"hello world").unwrap();
+		sequential.create_icon(&mut sequential_png, b"hello world").unwrap();
+
+		assert_ne!(legacy_png, sequential_png);
 	}
 }
@@ -0,0 +1,27 @@
+//! # blockies.rs
+//!
+//! library that generates blocky identicons
+//!
+//! Rust implementation of javascript [blockies](https://github.com/download13/blockies) library.
+//!
+//! ### Library usage
+//!
+//! ```rust
+//! use blockies::Classic;
+//!
+//! let blockies: Classic = Classic::default();
+//! let mut png = Vec::new();
+//!
+//! blockies.create_icon(&mut png, b"0x0000000000000000000000000000000000000000").unwrap();
+//!
+//! // `png` now contains a rendered image of the blockies for that address
+//! assert!(png.len() > 0);
+//! ```
+
+mod classic;
+mod rng;
+pub(crate) mod util;
+
+pub use classic::Classic;
+pub use rng::{BlockiesRng, LegacySinRng};
+pub use pixelate::Error;
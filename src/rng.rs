@@ -0,0 +1,46 @@
+/// A pluggable pseudo-random number generator for blockies seed expansion.
+///
+/// Implement this to swap the legacy `sin`-based generator for something
+/// cryptographically stronger or standards-based, e.g. an adapter around one
+/// of `rand`'s `SeedableRng`/`RngCore` types.
+pub trait BlockiesRng {
+	/// Construct the generator from the raw seed bytes.
+	fn from_seed(seed: &[u8]) -> Self;
+
+	/// Produce the next pseudo-random value in `[0.0, 1.0)`.
+	fn next_f64(&mut self) -> f64;
+}
+
+/// The `sin`-based generator used by the reference JavaScript implementation.
+///
+/// This is the default [`BlockiesRng`] for [`Classic`](crate::Classic), kept
+/// so existing output does not change.
+pub struct LegacySinRng {
+	randseed: f64,
+}
+
+impl BlockiesRng for LegacySinRng {
+	fn from_seed(seed: &[u8]) -> Self {
+		let mut randseed = 0u64;
+
+		for i in 0..seed.len() / 2 {
+			let h = ((seed[i * 2] as u64) << 8) | seed[i * 2 + 1] as u64;
+			randseed ^= h;
+		}
+
+		if seed.len() % 2 == 1 {
+			randseed ^= (seed[seed.len() - 1] as u64) << 8;
+		}
+
+		LegacySinRng {
+			randseed: randseed as f64,
+		}
+	}
+
+	fn next_f64(&mut self) -> f64 {
+		let n = (self.randseed.sin() + 1.0) / 2.0;
+		self.randseed += 1.0;
+		let r = n * 10000.0;
+		r - r.floor()
+	}
+}
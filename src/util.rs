@@ -0,0 +1,39 @@
+use hsl::HSL;
+use pixelate::Color;
+
+/// Create a buffer of image data where one item corresponds to one square on
+/// the blockies identicon. `fill` is invoked once per unique cell (the
+/// right half of each row is mirrored from the left half) and should return
+/// the palette index for that cell.
+pub fn create_image_data<F: FnMut() -> u8>(size: usize, mut fill: F) -> Vec<u8> {
+	let data_width = size / 2;
+	let row_width = size + size % 2;
+
+	let mut data = vec![0; size * row_width];
+
+	for row in data.chunks_mut(row_width) {
+		// `right` is going to be 1 item longer if size is odd, but that's fine
+		// as we are zipping it with reverse iterator
+		let (left, right) = row.split_at_mut(data_width);
+
+		for (left, right) in left.iter_mut().zip(right.iter_mut().rev()) {
+			let pixel = fill();
+
+			*left = pixel;
+			*right = pixel;
+		}
+
+		// Technically only have to do this for odd-sized images, but eh
+		if let Some(midpoint) = left.last() {
+			right[0] = *midpoint;
+		}
+	}
+
+	data
+}
+
+pub fn hsl_to_rgb(hsl: HSL) -> Color {
+	let (r, g, b) = hsl.to_rgb();
+
+	Color::Rgb(r, g, b)
+}